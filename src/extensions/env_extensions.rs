@@ -0,0 +1,279 @@
+use soroban_sdk::{contracttype, panic_with_error, Address, Env, Vec};
+
+use crate::types::{
+    asset::Asset, error::Error, subscription::Subscription, subscription_status::SubscriptionStatus,
+};
+
+// Max subscription IDs tracked per asset pair, bounding the ledger write
+// cost of a single `index_subscription` call.
+pub(crate) const MAX_INDEXED_SUBSCRIPTIONS: u32 = 200;
+
+#[contracttype]
+#[derive(Clone)]
+enum DataKey {
+    Admin,
+    Fee,
+    Token,
+    Normalizer,
+    MaxUpdateFactor,
+    MaxThresholdFactor,
+    Oracle,
+    LastSubscriptionId,
+    Subscription(u64),
+    AssetPairIndex(Asset, Asset),
+}
+
+pub trait EnvExtensions {
+    fn is_initialized(&self) -> bool;
+
+    fn get_admin(&self) -> Option<Address>;
+
+    fn set_admin(&self, admin: &Address);
+
+    fn panic_if_not_admin(&self);
+
+    fn get_fee(&self) -> u64;
+
+    fn set_fee(&self, fee: u64);
+
+    fn get_normalizer(&self) -> u64;
+
+    fn set_normalizer(&self, normalizer: u64);
+
+    fn get_max_update_factor(&self) -> Option<u64>;
+
+    fn set_max_update_factor(&self, max_update_factor: Option<u64>);
+
+    fn get_max_threshold_factor(&self) -> Option<u64>;
+
+    fn set_max_threshold_factor(&self, max_threshold_factor: Option<u64>);
+
+    fn get_token(&self) -> Address;
+
+    fn set_token(&self, token: &Address);
+
+    fn get_oracle(&self) -> Address;
+
+    fn set_oracle(&self, oracle: &Address);
+
+    fn get_last_subscription_id(&self) -> u64;
+
+    fn set_last_subscription_id(&self, subscription_id: u64);
+
+    fn get_subscription(&self, subscription_id: u64) -> Option<Subscription>;
+
+    fn set_subscription(&self, subscription_id: u64, subscription: &Subscription);
+
+    fn remove_subscription(&self, subscription_id: u64);
+
+    fn extend_subscription_ttl(&self, subscription_id: u64, ledgers_to_live: u32);
+
+    // Returns the subscription IDs indexed under the given asset pair.
+    fn get_indexed_subscriptions(&self, base: &Asset, quote: &Asset) -> Vec<u64>;
+
+    // Adds `subscription_id` to the asset pair's index, pruning entries that are no
+    // longer eligible for notification (cancelled or suspended) along the way. An ID
+    // already present in the index is never subject to the cap, so a suspended
+    // subscription that still holds its slot can reclaim it on reactivation. Once a
+    // later call has pruned it, re-adding it is subject to the cap like any new ID.
+    //
+    // # Panics
+    //
+    // Panics if `subscription_id` isn't already indexed and the index is already at
+    // `MAX_INDEXED_SUBSCRIPTIONS` after pruning
+    fn index_subscription(&self, base: &Asset, quote: &Asset, subscription_id: u64);
+
+    // Removes `subscription_id` from the asset pair's index.
+    fn deindex_subscription(&self, base: &Asset, quote: &Asset, subscription_id: u64);
+}
+
+impl EnvExtensions for Env {
+    fn is_initialized(&self) -> bool {
+        self.storage().instance().has(&DataKey::Admin)
+    }
+
+    fn get_admin(&self) -> Option<Address> {
+        self.storage().instance().get(&DataKey::Admin)
+    }
+
+    fn set_admin(&self, admin: &Address) {
+        self.storage().instance().set(&DataKey::Admin, admin);
+    }
+
+    fn panic_if_not_admin(&self) {
+        let admin = self
+            .get_admin()
+            .unwrap_or_else(|| panic_with_error!(self, Error::NotInitialized));
+        admin.require_auth();
+    }
+
+    fn get_fee(&self) -> u64 {
+        self.storage().instance().get(&DataKey::Fee).unwrap_or(0)
+    }
+
+    fn set_fee(&self, fee: u64) {
+        self.storage().instance().set(&DataKey::Fee, &fee);
+    }
+
+    fn get_normalizer(&self) -> u64 {
+        self.storage()
+            .instance()
+            .get(&DataKey::Normalizer)
+            .unwrap_or(1)
+    }
+
+    fn set_normalizer(&self, normalizer: u64) {
+        self.storage()
+            .instance()
+            .set(&DataKey::Normalizer, &normalizer);
+    }
+
+    fn get_max_update_factor(&self) -> Option<u64> {
+        self.storage().instance().get(&DataKey::MaxUpdateFactor)
+    }
+
+    fn set_max_update_factor(&self, max_update_factor: Option<u64>) {
+        match max_update_factor {
+            Some(value) => self
+                .storage()
+                .instance()
+                .set(&DataKey::MaxUpdateFactor, &value),
+            None => self.storage().instance().remove(&DataKey::MaxUpdateFactor),
+        }
+    }
+
+    fn get_max_threshold_factor(&self) -> Option<u64> {
+        self.storage().instance().get(&DataKey::MaxThresholdFactor)
+    }
+
+    fn set_max_threshold_factor(&self, max_threshold_factor: Option<u64>) {
+        match max_threshold_factor {
+            Some(value) => self
+                .storage()
+                .instance()
+                .set(&DataKey::MaxThresholdFactor, &value),
+            None => self
+                .storage()
+                .instance()
+                .remove(&DataKey::MaxThresholdFactor),
+        }
+    }
+
+    fn get_token(&self) -> Address {
+        self.storage()
+            .instance()
+            .get(&DataKey::Token)
+            .unwrap_or_else(|| panic_with_error!(self, Error::NotInitialized))
+    }
+
+    fn set_token(&self, token: &Address) {
+        self.storage().instance().set(&DataKey::Token, token);
+    }
+
+    fn get_oracle(&self) -> Address {
+        self.storage()
+            .instance()
+            .get(&DataKey::Oracle)
+            .unwrap_or_else(|| panic_with_error!(self, Error::NotInitialized))
+    }
+
+    fn set_oracle(&self, oracle: &Address) {
+        self.storage().instance().set(&DataKey::Oracle, oracle);
+    }
+
+    fn get_last_subscription_id(&self) -> u64 {
+        self.storage()
+            .instance()
+            .get(&DataKey::LastSubscriptionId)
+            .unwrap_or(0)
+    }
+
+    fn set_last_subscription_id(&self, subscription_id: u64) {
+        self.storage()
+            .instance()
+            .set(&DataKey::LastSubscriptionId, &subscription_id);
+    }
+
+    fn get_subscription(&self, subscription_id: u64) -> Option<Subscription> {
+        self.storage()
+            .persistent()
+            .get(&DataKey::Subscription(subscription_id))
+    }
+
+    fn set_subscription(&self, subscription_id: u64, subscription: &Subscription) {
+        self.storage()
+            .persistent()
+            .set(&DataKey::Subscription(subscription_id), subscription);
+    }
+
+    fn remove_subscription(&self, subscription_id: u64) {
+        self.storage()
+            .persistent()
+            .remove(&DataKey::Subscription(subscription_id));
+    }
+
+    fn extend_subscription_ttl(&self, subscription_id: u64, ledgers_to_live: u32) {
+        self.storage().persistent().extend_ttl(
+            &DataKey::Subscription(subscription_id),
+            ledgers_to_live,
+            ledgers_to_live,
+        );
+    }
+
+    fn get_indexed_subscriptions(&self, base: &Asset, quote: &Asset) -> Vec<u64> {
+        self.storage()
+            .persistent()
+            .get(&DataKey::AssetPairIndex(base.clone(), quote.clone()))
+            .unwrap_or_else(|| Vec::new(self))
+    }
+
+    fn index_subscription(&self, base: &Asset, quote: &Asset, subscription_id: u64) {
+        let key = DataKey::AssetPairIndex(base.clone(), quote.clone());
+        let existing = self.get_indexed_subscriptions(base, quote);
+
+        // Prune entries that are cancelled or suspended: neither gets notified, so
+        // neither should keep eating into the cap. `subscription_id` itself is left
+        // out of this pass (and out of the cap check below) regardless of its current
+        // status - it's about to be (re-)added as the caller's own active entry, so a
+        // suspended subscription that hasn't been pruned yet can reclaim its slot.
+        let mut pruned = Vec::new(self);
+        let mut already_indexed = false;
+        for existing_id in existing.iter() {
+            if existing_id == subscription_id {
+                already_indexed = true;
+                continue;
+            }
+            let still_active = matches!(
+                self.get_subscription(existing_id),
+                Some(subscription) if subscription.status == SubscriptionStatus::Active
+            );
+            if still_active {
+                pruned.push_back(existing_id);
+            }
+        }
+
+        if !already_indexed && pruned.len() >= MAX_INDEXED_SUBSCRIPTIONS {
+            panic_with_error!(self, Error::AssetPairIndexFull);
+        }
+        pruned.push_back(subscription_id);
+        self.storage().persistent().set(&key, &pruned);
+    }
+
+    fn deindex_subscription(&self, base: &Asset, quote: &Asset, subscription_id: u64) {
+        let key = DataKey::AssetPairIndex(base.clone(), quote.clone());
+        let existing = self.get_indexed_subscriptions(base, quote);
+
+        let mut kept = Vec::new(self);
+        for id in existing.iter() {
+            if id != subscription_id {
+                kept.push_back(id);
+            }
+        }
+
+        if kept.is_empty() {
+            self.storage().persistent().remove(&key);
+        } else {
+            self.storage().persistent().set(&key, &kept);
+        }
+    }
+}
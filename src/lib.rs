@@ -1,22 +1,22 @@
 #![no_std]
 
 mod extensions;
+mod reflector_oracle_client;
 mod types;
 
 use extensions::env_extensions::EnvExtensions;
+use reflector_oracle_client::ReflectorOracleClient;
 use soroban_sdk::{
     contract, contractimpl, panic_with_error, symbol_short, token::TokenClient, Address, BytesN, Env, Symbol, Vec
 };
 use types::{
-    contract_config::ContractConfig, error::Error, subscription::Subscription,
+    asset::Asset, contract_config::ContractConfig, error::Error, subscription::Subscription,
     subscription_init_params::SubscriptionInitParams, subscription_status::SubscriptionStatus,
+    subscription_update_params::SubscriptionUpdateParams,
 };
 
 const REFLECTOR: Symbol = symbol_short!("reflector");
 
-// 1 day in milliseconds
-const DAY: u64 = 86400 * 1000;
-
 const MAX_WEBHOOK_SIZE: u32 = 2048;
 
 // Minimum heartbeat in minutes
@@ -43,25 +43,48 @@ impl SubscriptionContract {
         if e.is_initialized() {
             e.panic_with_error(Error::AlreadyInitialized);
         }
+        if config.normalizer == 0 {
+            e.panic_with_error(Error::InvalidAmount);
+        }
 
         e.set_admin(&config.admin);
         e.set_fee(config.fee);
+        e.set_normalizer(config.normalizer);
+        e.set_max_update_factor(config.max_update_factor);
+        e.set_max_threshold_factor(config.max_threshold_factor);
         e.set_token(&config.token);
+        e.set_oracle(&config.oracle);
         e.set_last_subscription_id(0);
     }
 
-    // Sets the base fee for the contract. Can be invoked only by the admin account.
+    // Sets the fee curve for the contract. Can be invoked only by the admin account.
     //
     // # Arguments
     //
     // * `fee` - New base fee
+    // * `normalizer` - Divisor applied to the update/threshold-weighted fee
+    // * `max_update_factor` - Optional cap on the `updates_per_day` factor
+    // * `max_threshold_factor` - Optional cap on the `threshold_weight` factor
     //
     // # Panics
     //
     // Panics if the caller doesn't match admin address
-    pub fn set_fee(e: Env, fee: u64) {
+    // Panics if `normalizer` is zero
+    pub fn set_fee(
+        e: Env,
+        fee: u64,
+        normalizer: u64,
+        max_update_factor: Option<u64>,
+        max_threshold_factor: Option<u64>,
+    ) {
         e.panic_if_not_admin();
+        if normalizer == 0 {
+            e.panic_with_error(Error::InvalidAmount);
+        }
         e.set_fee(fee);
+        e.set_normalizer(normalizer);
+        e.set_max_update_factor(max_update_factor);
+        e.set_max_threshold_factor(max_threshold_factor);
     }
 
     // Triggers the subscription. Can be invoked only by the admin account.
@@ -82,6 +105,63 @@ impl SubscriptionContract {
         );
     }
 
+    // Notifies subscriptions whose asset pair was just updated. Can be invoked only by the
+    // admin account.
+    //
+    // For every `(base, quote, price, prev_price)` tuple, only the subscriptions indexed
+    // against that pair are considered, and only those that are `Active` and whose relative
+    // price move meets their `threshold`, or whose `heartbeat` has elapsed, are notified.
+    //
+    // # Arguments
+    //
+    // * `timestamp` - Timestamp of the price update
+    // * `updates` - `(base, quote, price, prev_price)` tuples for every asset pair that changed
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address
+    pub fn trigger_prices(
+        e: Env,
+        timestamp: u64,
+        updates: Vec<(Asset, Asset, i128, i128)>,
+    ) {
+        e.panic_if_not_admin();
+
+        for (base, quote, price, prev_price) in updates.iter() {
+            let subscription_ids = e.get_indexed_subscriptions(&base, &quote);
+            for subscription_id in subscription_ids.iter() {
+                let subscription = match e.get_subscription(subscription_id) {
+                    Some(subscription) => subscription,
+                    // Cancelled since it was indexed; drop it from the index.
+                    None => {
+                        e.deindex_subscription(&base, &quote, subscription_id);
+                        continue;
+                    }
+                };
+                if subscription.status != SubscriptionStatus::Active {
+                    // Suspended subscriptions aren't served; drop them from the index.
+                    e.deindex_subscription(&base, &quote, subscription_id);
+                    continue;
+                }
+
+                let moved_bps = if prev_price == 0 {
+                    0
+                } else {
+                    (price - prev_price).abs() * 10000 / prev_price.abs()
+                };
+                let heartbeat_elapsed =
+                    timestamp - subscription.updated >= (subscription.heartbeat as u64) * 60 * 1000;
+
+                if moved_bps >= subscription.threshold as i128 || heartbeat_elapsed {
+                    e.events().publish(
+                        (REFLECTOR, symbol_short!("notified"), subscription.owner),
+                        (subscription_id, subscription.webhook),
+                    );
+                }
+            }
+        }
+    }
+
     // Updates the contract source code. Can be invoked only by the admin account.
     //
     // # Arguments
@@ -108,46 +188,12 @@ impl SubscriptionContract {
     // Panics if the caller doesn't match admin address
     pub fn charge(e: Env, subscription_ids: Vec<u64>) {
         e.panic_if_not_admin();
-        let mut total_charge: u64 = 0;
         let now = now(&e);
+        let oracle = ReflectorOracleClient::new(&e, &e.get_oracle());
+
+        let mut total_charge: u64 = 0;
         for subscription_id in subscription_ids.iter() {
-            if let Some(mut subscription) = e.get_subscription(subscription_id) {
-                let days = (now - subscription.updated) / DAY;
-                if days == 0 {
-                    continue;
-                }
-                let fee = calc_fee(&e, &subscription.heartbeat, &subscription.threshold);
-                let mut charge = days * fee;
-                if subscription.balance < charge {
-                    charge = subscription.balance;
-                }
-                subscription.balance -= charge;
-                subscription.updated = now;
-                if subscription.balance < fee {
-                    // Deactivate the subscription if the balance is less than the fee
-                    subscription.status = SubscriptionStatus::Suspended;
-                    e.events().publish(
-                        (
-                            REFLECTOR,
-                            symbol_short!("suspended"),
-                            subscription.owner.clone(),
-                        ),
-                        (now, subscription_id),
-                    );
-                }
-                e.set_subscription(subscription_id, &subscription);
-
-                e.events().publish(
-                    (
-                        REFLECTOR,
-                        symbol_short!("charged"),
-                        subscription.owner,
-                    ),
-                    (now, subscription_id, charge),
-                );
-
-                total_charge += charge;
-            }
+            total_charge += charge_subscription(&e, &oracle, subscription_id, now);
         }
         // If there is nothing to charge, return
         if total_charge == 0 {
@@ -158,6 +204,48 @@ impl SubscriptionContract {
         get_token_client(&e).burn(&e.current_contract_address(), &(total_charge as i128));
     }
 
+    // Charges every subscription in `[start_id, start_id + limit)` in one batch, without
+    // requiring the admin to enumerate IDs off-chain. Can be invoked only by the admin account.
+    //
+    // # Arguments
+    //
+    // * `start_id` - First subscription ID to process
+    // * `limit` - Maximum number of subscription IDs to process in this call
+    //
+    // # Returns
+    //
+    // The next `start_id` to resume from, or 0 once the end of the subscription range is reached
+    //
+    // # Panics
+    //
+    // Panics if the caller doesn't match admin address
+    pub fn charge_all(e: Env, start_id: u64, limit: u32) -> u64 {
+        e.panic_if_not_admin();
+        let last_id = e.get_last_subscription_id();
+        if start_id > last_id {
+            return 0;
+        }
+        let end_id = (start_id + limit as u64).saturating_sub(1).min(last_id);
+
+        let now = now(&e);
+        let oracle = ReflectorOracleClient::new(&e, &e.get_oracle());
+
+        let mut total_charge: u64 = 0;
+        for subscription_id in start_id..=end_id {
+            total_charge += charge_subscription(&e, &oracle, subscription_id, now);
+        }
+
+        if total_charge > 0 {
+            get_token_client(&e).burn(&e.current_contract_address(), &(total_charge as i128));
+        }
+
+        if end_id >= last_id {
+            0
+        } else {
+            end_id + 1
+        }
+    }
+
     // Public
 
     // Creates a new subscription.
@@ -187,14 +275,6 @@ impl SubscriptionContract {
         // Check the authorization
         new_subscription.owner.require_auth();
 
-        let subscription_fee = calc_fee(&e, &new_subscription.heartbeat, &new_subscription.threshold);
-
-        // Check the amount
-        let init_fee = subscription_fee * 2; // init fee is 2 times the subscription fee
-        if amount < init_fee {
-            e.panic_with_error(Error::InvalidAmount);
-        }
-
         if MIN_HEARTBEAT > new_subscription.heartbeat {
             e.panic_with_error(Error::InvalidHeartbeat);
         }
@@ -207,6 +287,14 @@ impl SubscriptionContract {
             e.panic_with_error(Error::WebhookTooLong);
         }
 
+        let subscription_fee = calc_fee(&e, &new_subscription.heartbeat, &new_subscription.threshold);
+
+        // Check the amount
+        let init_fee = subscription_fee * 2; // init fee is 2 times the subscription fee
+        if amount < init_fee {
+            e.panic_with_error(Error::InvalidAmount);
+        }
+
         // Transfer and burn the tokens
         transfer_tokens_to_current_contract(&e, &new_subscription.owner, amount, init_fee);
 
@@ -222,10 +310,12 @@ impl SubscriptionContract {
             balance: amount - init_fee,
             status: SubscriptionStatus::Active,
             updated: now(&e), // normalize to milliseconds
+            last_price: 0,
         };
         e.set_subscription(subscription_id, &subscription);
         e.set_last_subscription_id(subscription_id);
-        
+        e.index_subscription(&subscription.base, &subscription.quote, subscription_id);
+
         e.extend_subscription_ttl(subscription_id, calc_ledgers_to_live(&e, &subscription_fee, &subscription.balance));
         let data = (subscription_id, subscription.clone());
         e.events()
@@ -269,6 +359,10 @@ impl SubscriptionContract {
                 // Set the activation fee as the burn amount
                 burn_amount = subscription_fee;
                 subscription.status = SubscriptionStatus::Active;
+                // trigger_prices prunes Suspended entries from the index lazily; make sure
+                // reactivating here re-indexes, or this subscription stops being notified
+                // even though it's Active again.
+                e.index_subscription(&subscription.base, &subscription.quote, subscription_id);
             },
             _ => {}
         }
@@ -285,7 +379,146 @@ impl SubscriptionContract {
         );
     }
 
-    // Withdraws funds from the subscription and deactivates it.
+    // Reconfigures an existing subscription's pair/threshold/heartbeat/webhook in place,
+    // so subscribers don't have to `cancel` and recreate to change their settings.
+    //
+    // # Arguments
+    //
+    // * `subscription_id` - Subscription ID
+    // * `new_params` - New subscription parameters
+    //
+    // # Panics
+    //
+    // Panics if the contract is not initialized
+    // Panics if the subscription does not exist
+    // Panics if the caller doesn't match the owner address
+    // Panics if the subscription is not active
+    // Panics if the new heartbeat, threshold or webhook size is out of bounds
+    // Panics if the new fee is higher and the remaining balance can't cover at least one period
+    pub fn update_subscription(e: Env, subscription_id: u64, new_params: SubscriptionUpdateParams) {
+        panic_if_not_initialized(&e);
+        let mut subscription = e
+            .get_subscription(subscription_id)
+            .unwrap_or_else(|| panic_with_error!(e, Error::SubscriptionNotFound));
+        subscription.owner.require_auth();
+        match subscription.status {
+            SubscriptionStatus::Active => {}
+            _ => {
+                e.panic_with_error(Error::InvalidSubscriptionStatusError);
+            }
+        }
+
+        if MIN_HEARTBEAT > new_params.heartbeat {
+            e.panic_with_error(Error::InvalidHeartbeat);
+        }
+        if new_params.threshold == 0 || new_params.threshold > 10000 {
+            e.panic_with_error(Error::InvalidThreshold);
+        }
+        if new_params.webhook.len() > MAX_WEBHOOK_SIZE {
+            e.panic_with_error(Error::WebhookTooLong);
+        }
+
+        let old_fee = calc_fee(&e, &subscription.heartbeat, &subscription.threshold);
+        let new_fee = calc_fee(&e, &new_params.heartbeat, &new_params.threshold);
+        if new_fee > old_fee && subscription.balance < new_fee {
+            e.panic_with_error(Error::InvalidAmount);
+        }
+
+        let old_subscription = subscription.clone();
+        let pair_changed = new_params.base != subscription.base || new_params.quote != subscription.quote;
+        if pair_changed {
+            e.deindex_subscription(&subscription.base, &subscription.quote, subscription_id);
+        }
+
+        subscription.base = new_params.base;
+        subscription.quote = new_params.quote;
+        subscription.threshold = new_params.threshold;
+        subscription.heartbeat = new_params.heartbeat;
+        subscription.webhook = new_params.webhook;
+
+        if pair_changed {
+            e.index_subscription(&subscription.base, &subscription.quote, subscription_id);
+            // The previously observed price was for the old pair; forget it.
+            subscription.last_price = 0;
+        }
+
+        e.set_subscription(subscription_id, &subscription);
+        e.extend_subscription_ttl(
+            subscription_id,
+            calc_ledgers_to_live(&e, &new_fee, &subscription.balance),
+        );
+
+        e.events().publish(
+            (REFLECTOR, symbol_short!("updated"), subscription.owner.clone()),
+            (subscription_id, old_subscription, subscription),
+        );
+    }
+
+    // Withdraws part of the balance from the subscription, keeping it active.
+    //
+    // # Arguments
+    //
+    // * `subscription_id` - Subscription ID
+    // * `amount` - Amount to withdraw, or `u64::MAX` to withdraw everything down to one fee of runway
+    //
+    // # Panics
+    //
+    // Panics if the contract is not initialized
+    // Panics if the subscription does not exist
+    // Panics if the caller doesn't match the owner address
+    // Panics if the subscription is not active
+    // Panics if withdrawing `amount` would leave less than one fee period of balance
+    // Panics if the token transfer fails
+    pub fn withdraw(e: Env, subscription_id: u64, amount: u64) {
+        panic_if_not_initialized(&e);
+        let mut subscription = e
+            .get_subscription(subscription_id)
+            .unwrap_or_else(|| panic_with_error!(e, Error::SubscriptionNotFound));
+        subscription.owner.require_auth();
+        match subscription.status {
+            SubscriptionStatus::Active => {}
+            _ => {
+                e.panic_with_error(Error::InvalidSubscriptionStatusError);
+            }
+        }
+
+        let fee = calc_fee(&e, &subscription.heartbeat, &subscription.threshold);
+
+        // u64::MAX is the sentinel for "withdraw the maximum while leaving exactly one fee of runway"
+        let amount = if amount == u64::MAX {
+            if subscription.balance <= fee {
+                e.panic_with_error(Error::InvalidAmount);
+            }
+            subscription.balance - fee
+        } else {
+            amount
+        };
+
+        if amount == 0 || amount > subscription.balance || subscription.balance - amount < fee {
+            e.panic_with_error(Error::InvalidAmount);
+        }
+
+        subscription.balance -= amount;
+        e.set_subscription(subscription_id, &subscription);
+        e.extend_subscription_ttl(
+            subscription_id,
+            calc_ledgers_to_live(&e, &fee, &subscription.balance),
+        );
+
+        transfer_tokens(
+            &e,
+            &e.current_contract_address(),
+            &subscription.owner,
+            amount,
+        );
+
+        e.events().publish(
+            (REFLECTOR, symbol_short!("withdrawn"), subscription.owner.clone()),
+            (subscription_id, subscription, amount),
+        );
+    }
+
+    // Withdraws the remaining balance from the subscription and removes it entirely.
     //
     // # Arguments
     //
@@ -315,6 +548,7 @@ impl SubscriptionContract {
             subscription.balance,
         );
         e.remove_subscription(subscription_id);
+        e.deindex_subscription(&subscription.base, &subscription.quote, subscription_id);
         e.events()
             .publish((REFLECTOR, symbol_short!("cancelled"), subscription.owner), subscription_id);
     }
@@ -418,9 +652,105 @@ fn now(e: &Env) -> u64 {
     e.ledger().timestamp() * 1000 // normalize to milliseconds
 }
 
+// Charges a single subscription if the oracle shows work was actually due for it - either its
+// price moved past `threshold` since it was last served, or its `heartbeat` elapsed. Returns the
+// amount charged (0 if the subscription wasn't found, wasn't Active, or wasn't due).
+fn charge_subscription(
+    e: &Env,
+    oracle: &ReflectorOracleClient,
+    subscription_id: u64,
+    now: u64,
+) -> u64 {
+    let mut subscription = match e.get_subscription(subscription_id) {
+        Some(subscription) => subscription,
+        None => return 0,
+    };
+    if subscription.status != SubscriptionStatus::Active {
+        return 0;
+    }
+
+    let heartbeat_elapsed =
+        now - subscription.updated >= (subscription.heartbeat as u64) * 60 * 1000;
+
+    let (observed_price, observed_timestamp) =
+        match oracle.last_price(&subscription.base, &subscription.quote) {
+            Some(price_data) => (price_data.price, price_data.timestamp),
+            // Oracle has no data for this pair yet; nothing is provably due.
+            None => return 0,
+        };
+    let moved_bps = if subscription.last_price == 0 {
+        0
+    } else {
+        (observed_price - subscription.last_price).abs() * 10000 / subscription.last_price.abs()
+    };
+
+    if moved_bps < subscription.threshold as i128 && !heartbeat_elapsed {
+        return 0;
+    }
+
+    let fee = calc_fee(e, &subscription.heartbeat, &subscription.threshold);
+    let mut charge = fee;
+    if subscription.balance < charge {
+        charge = subscription.balance;
+    }
+    subscription.balance -= charge;
+    subscription.updated = now;
+    subscription.last_price = observed_price;
+    if subscription.balance < fee {
+        // Deactivate the subscription if the balance is less than the fee
+        subscription.status = SubscriptionStatus::Suspended;
+        e.events().publish(
+            (
+                REFLECTOR,
+                symbol_short!("suspended"),
+                subscription.owner.clone(),
+            ),
+            (now, subscription_id),
+        );
+    }
+    e.set_subscription(subscription_id, &subscription);
+
+    // Decimals and the oracle's own observation timestamp ride along so a `charged`
+    // event is self-contained for auditing without a follow-up oracle query.
+    let observed_decimals = oracle.decimals();
+    e.events().publish(
+        (REFLECTOR, symbol_short!("charged"), subscription.owner),
+        (
+            now,
+            subscription_id,
+            charge,
+            observed_price,
+            observed_decimals,
+            observed_timestamp,
+        ),
+    );
+
+    charge
+}
+
 fn calc_fee(e: &Env, heartbeat: &u32, threshold: &u32) -> u64 {
-    //implement the fee calculation logic here
-    e.get_fee()
+    let base_fee = e.get_fee();
+
+    let mut updates_per_day = 1440u64 / (*heartbeat as u64);
+    if let Some(cap) = e.get_max_update_factor() {
+        updates_per_day = updates_per_day.min(cap);
+    }
+
+    let mut threshold_weight = 10000u64 / (*threshold as u64);
+    if let Some(cap) = e.get_max_threshold_factor() {
+        threshold_weight = threshold_weight.min(cap);
+    }
+
+    let weighted = base_fee
+        .saturating_mul(updates_per_day)
+        .saturating_mul(threshold_weight);
+
+    ceil_div(weighted, e.get_normalizer()).max(1)
+}
+
+// Integer division rounded up, so a tiny weighted fee never normalizes to zero.
+fn ceil_div(numerator: u64, denominator: u64) -> u64 {
+    (numerator + denominator - 1) / denominator
 }
 
 fn calc_ledgers_to_live(e: &Env, fee: &u64, amount: &u64) -> u32 {
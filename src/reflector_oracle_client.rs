@@ -0,0 +1,45 @@
+// Minimal cross-contract client for the Reflector price oracle. Hand-rolled in place of a
+// `contractimport!`-generated client, since only the interface we call is needed here.
+use soroban_sdk::{contracttype, symbol_short, vec, Address, Env, IntoVal};
+
+use crate::types::asset::Asset;
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+pub struct ReflectorOracleClient<'a> {
+    env: &'a Env,
+    address: Address,
+}
+
+impl<'a> ReflectorOracleClient<'a> {
+    pub fn new(env: &'a Env, address: &Address) -> Self {
+        Self {
+            env,
+            address: address.clone(),
+        }
+    }
+
+    // Reads the latest known price for the asset pair, if the oracle has one.
+    pub fn last_price(&self, base: &Asset, quote: &Asset) -> Option<PriceData> {
+        self.env.invoke_contract(
+            &self.address,
+            &symbol_short!("lastprice"),
+            vec![
+                self.env,
+                base.clone().into_val(self.env),
+                quote.clone().into_val(self.env),
+            ],
+        )
+    }
+
+    // Number of decimal places the oracle reports prices in.
+    pub fn decimals(&self) -> u32 {
+        self.env
+            .invoke_contract(&self.address, &symbol_short!("decimals"), vec![self.env])
+    }
+}
@@ -0,0 +1,588 @@
+#![cfg(test)]
+
+use soroban_sdk::{
+    contract, contractimpl,
+    testutils::{Address as _, Ledger},
+    token, Address, Bytes, Env, IntoVal,
+};
+
+use crate::{
+    extensions::env_extensions::{EnvExtensions, MAX_INDEXED_SUBSCRIPTIONS},
+    reflector_oracle_client::PriceData,
+    types::{
+        asset::Asset, contract_config::ContractConfig,
+        subscription_init_params::SubscriptionInitParams, subscription_status::SubscriptionStatus,
+    },
+    SubscriptionContract, SubscriptionContractClient, REFLECTOR,
+};
+
+// Stands in for the real Reflector oracle contract in tests: always reports a fixed price.
+#[contract]
+struct StubOracle;
+
+#[contractimpl]
+impl StubOracle {
+    pub fn lastprice(e: Env, _base: Asset, _quote: Asset) -> Option<PriceData> {
+        Some(PriceData {
+            price: 100,
+            timestamp: e.ledger().timestamp(),
+        })
+    }
+
+    pub fn decimals(_e: Env) -> u32 {
+        14
+    }
+}
+
+// Stands in for an oracle that has never seen this pair: always reports no price.
+#[contract]
+struct StubOracleNoPrice;
+
+#[contractimpl]
+impl StubOracleNoPrice {
+    pub fn lastprice(_e: Env, _base: Asset, _quote: Asset) -> Option<PriceData> {
+        None
+    }
+
+    pub fn decimals(_e: Env) -> u32 {
+        14
+    }
+}
+
+fn create_token<'a>(e: &Env, admin: &Address) -> (Address, token::StellarAssetClient<'a>) {
+    let sac = e.register_stellar_asset_contract_v2(admin.clone());
+    let token_address = sac.address();
+    (
+        token_address.clone(),
+        token::StellarAssetClient::new(e, &token_address),
+    )
+}
+
+fn init_contract<'a>(e: &Env) -> (SubscriptionContractClient<'a>, Address, Address) {
+    let oracle = e.register_contract(None, StubOracle);
+    init_contract_with_oracle(e, oracle)
+}
+
+fn init_contract_with_oracle<'a>(
+    e: &Env,
+    oracle: Address,
+) -> (SubscriptionContractClient<'a>, Address, Address) {
+    let admin = Address::generate(e);
+    let (token, token_admin_client) = create_token(e, &admin);
+    let contract_id = e.register_contract(None, SubscriptionContract);
+    let client = SubscriptionContractClient::new(e, &contract_id);
+    client.config(&ContractConfig {
+        admin: admin.clone(),
+        fee: 100,
+        token: token.clone(),
+        oracle,
+        normalizer: 2400,
+        max_update_factor: None,
+        max_threshold_factor: None,
+    });
+    token_admin_client.mint(&admin, &1_000_000);
+    (client, admin, token)
+}
+
+fn default_subscription(e: &Env, owner: &Address) -> SubscriptionInitParams {
+    SubscriptionInitParams {
+        owner: owner.clone(),
+        base: Asset::Other(soroban_sdk::symbol_short!("BASE")),
+        quote: Asset::Other(soroban_sdk::symbol_short!("QUOTE")),
+        threshold: 100,
+        heartbeat: 60,
+        webhook: Bytes::from_slice(e, b"https://example.com/hook"),
+    }
+}
+
+fn default_update_params(
+    e: &Env,
+    threshold: u32,
+    heartbeat: u32,
+) -> crate::types::subscription_update_params::SubscriptionUpdateParams {
+    crate::types::subscription_update_params::SubscriptionUpdateParams {
+        base: Asset::Other(soroban_sdk::symbol_short!("BASE")),
+        quote: Asset::Other(soroban_sdk::symbol_short!("QUOTE")),
+        threshold,
+        heartbeat,
+        webhook: Bytes::from_slice(e, b"https://example.com/hook"),
+    }
+}
+
+#[test]
+fn test_create_and_get_subscription() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, token) = init_contract(&e);
+    let token_client = token::TokenClient::new(&e, &token);
+    token_client.transfer(&admin, &admin, &0); // sanity: token is usable
+
+    let (subscription_id, subscription) =
+        client.create_subscription(&default_subscription(&e, &admin), &1000);
+
+    assert_eq!(subscription_id, 1);
+    assert_eq!(subscription.status, SubscriptionStatus::Active);
+    assert_eq!(client.last_id(), 1);
+    assert_eq!(client.get_subscription(&subscription_id).balance, subscription.balance);
+}
+
+#[test]
+fn test_deposit_increases_balance() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, subscription) =
+        client.create_subscription(&default_subscription(&e, &admin), &1000);
+    let balance_before = subscription.balance;
+
+    client.deposit(&admin, &subscription_id, &500);
+
+    assert_eq!(
+        client.get_subscription(&subscription_id).balance,
+        balance_before + 500
+    );
+}
+
+#[test]
+fn test_update_subscription_reindexes_on_pair_change() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+
+    let new_base = Asset::Other(soroban_sdk::symbol_short!("NEWBASE"));
+    let new_quote = Asset::Other(soroban_sdk::symbol_short!("NEWQUOTE"));
+    client.update_subscription(
+        &subscription_id,
+        &crate::types::subscription_update_params::SubscriptionUpdateParams {
+            base: new_base.clone(),
+            quote: new_quote.clone(),
+            threshold: 50,
+            heartbeat: 120,
+            webhook: Bytes::from_slice(&e, b"https://example.com/new-hook"),
+        },
+    );
+
+    let subscription = client.get_subscription(&subscription_id);
+    assert_eq!(subscription.base, new_base);
+    assert_eq!(subscription.quote, new_quote);
+    assert_eq!(subscription.threshold, 50);
+    assert_eq!(subscription.heartbeat, 120);
+
+    let params = default_subscription(&e, &admin);
+    let (old_indexed, new_indexed) = e.as_contract(&client.address, || {
+        (
+            e.get_indexed_subscriptions(&params.base, &params.quote),
+            e.get_indexed_subscriptions(&new_base, &new_quote),
+        )
+    });
+    assert!(!old_indexed.contains(&subscription_id));
+    assert!(new_indexed.contains(&subscription_id));
+}
+
+#[test]
+fn test_update_subscription_rejects_invalid_heartbeat() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        client.update_subscription(&subscription_id, &default_update_params(&e, 100, 1))
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_update_subscription_rejects_invalid_threshold() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        client.update_subscription(&subscription_id, &default_update_params(&e, 0, 60))
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_update_subscription_rejects_oversized_webhook() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+
+    let mut params = default_update_params(&e, 100, 60);
+    params.webhook = Bytes::from_slice(&e, &[0u8; 2049]); // MAX_WEBHOOK_SIZE is 2048
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        client.update_subscription(&subscription_id, &params)
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_update_subscription_rejects_fee_increase_balance_cant_cover() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    // Covers only the init fee, leaving no balance to cover a higher new fee.
+    let (subscription_id, _) = client.create_subscription(&default_subscription(&e, &admin), &200);
+
+    // Tighter heartbeat and threshold both drive the fee up sharply.
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        client.update_subscription(&subscription_id, &default_update_params(&e, 1, 5))
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_update_subscription_rejects_when_not_active() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    // Init fee (200) plus half a fee (50), so the first heartbeat charge suspends it.
+    let (subscription_id, _) = client.create_subscription(&default_subscription(&e, &admin), &250);
+    e.ledger().with_mut(|l| l.timestamp += 3600); // one heartbeat (60 min) elapses
+    let mut ids = soroban_sdk::Vec::new(&e);
+    ids.push_back(subscription_id);
+    client.charge(&ids);
+    assert_eq!(
+        client.get_subscription(&subscription_id).status,
+        SubscriptionStatus::Suspended
+    );
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        client.update_subscription(&subscription_id, &default_update_params(&e, 100, 60))
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_update_subscription_rejects_non_owner() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+
+    e.set_auths(&[]); // nobody has authorized this call, so the owner check must fail
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        client.update_subscription(&subscription_id, &default_update_params(&e, 100, 60))
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_withdraw_keeps_subscription_active() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, subscription) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+    let balance_before = subscription.balance;
+
+    client.withdraw(&subscription_id, &1000);
+
+    let subscription = client.get_subscription(&subscription_id);
+    assert_eq!(subscription.balance, balance_before - 1000);
+    assert_eq!(subscription.status, SubscriptionStatus::Active);
+}
+
+#[test]
+fn test_withdraw_max_leaves_one_fee_of_runway() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+
+    client.withdraw(&subscription_id, &u64::MAX);
+
+    let subscription = client.get_subscription(&subscription_id);
+    assert_eq!(subscription.balance, 100); // one fee of runway remains
+}
+
+#[test]
+fn test_withdraw_rejects_amount_leaving_less_than_one_fee() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+    let balance = client.get_subscription(&subscription_id).balance; // 9800; fee is 100
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        client.withdraw(&subscription_id, &(balance - 50)) // leaves only 50, less than the 100 fee
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_withdraw_rejects_non_owner() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+
+    e.set_auths(&[]); // nobody has authorized this call, so the owner check must fail
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        client.withdraw(&subscription_id, &1000)
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_cancel_removes_subscription() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) = client.create_subscription(&default_subscription(&e, &admin), &1000);
+
+    client.cancel(&subscription_id);
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        client.get_subscription(&subscription_id)
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_charge_is_noop_before_heartbeat_elapses_or_price_moves() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+    let balance_before = client.get_subscription(&subscription_id).balance;
+
+    // StubOracle always reports the same price, so with no time elapsed neither the
+    // heartbeat nor the threshold is due yet.
+    let mut ids = soroban_sdk::Vec::new(&e);
+    ids.push_back(subscription_id);
+    client.charge(&ids);
+
+    let subscription = client.get_subscription(&subscription_id);
+    assert_eq!(subscription.balance, balance_before);
+    assert_eq!(subscription.status, SubscriptionStatus::Active);
+}
+
+#[test]
+fn test_charge_is_noop_when_oracle_has_no_price() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let oracle = e.register_contract(None, StubOracleNoPrice);
+    let (client, admin, _token) = init_contract_with_oracle(&e, oracle);
+    let (subscription_id, _) =
+        client.create_subscription(&default_subscription(&e, &admin), &10000);
+    let balance_before = client.get_subscription(&subscription_id).balance;
+
+    e.ledger().with_mut(|l| l.timestamp += 2 * 86400); // heartbeat elapsed, but no price to go on
+
+    let mut ids = soroban_sdk::Vec::new(&e);
+    ids.push_back(subscription_id);
+    client.charge(&ids);
+
+    let subscription = client.get_subscription(&subscription_id);
+    assert_eq!(subscription.balance, balance_before);
+    assert_eq!(subscription.status, SubscriptionStatus::Active);
+}
+
+#[test]
+fn test_charge_after_heartbeat_elapses() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (subscription_id, _) = client.create_subscription(&default_subscription(&e, &admin), &10000);
+
+    e.ledger().with_mut(|l| l.timestamp += 2 * 86400);
+
+    let mut ids = soroban_sdk::Vec::new(&e);
+    ids.push_back(subscription_id);
+    client.charge(&ids);
+
+    let subscription = client.get_subscription(&subscription_id);
+    assert_eq!(subscription.balance, 10000 - 200 - 100); // init fee (2x) + one served fee
+}
+
+#[test]
+fn test_charge_all_pages_through_subscriptions() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let (id1, _) = client.create_subscription(&default_subscription(&e, &admin), &10000);
+    let (id2, _) = client.create_subscription(&default_subscription(&e, &admin), &10000);
+    let (id3, _) = client.create_subscription(&default_subscription(&e, &admin), &10000);
+
+    e.ledger().with_mut(|l| l.timestamp += 2 * 86400);
+
+    let cursor = client.charge_all(&id1, &1);
+    assert_eq!(cursor, id2);
+    assert_eq!(client.get_subscription(&id1).balance, 10000 - 200 - 100);
+    assert_eq!(client.get_subscription(&id2).balance, 10000 - 200); // not yet charged
+
+    let cursor = client.charge_all(&cursor, &10);
+    assert_eq!(cursor, 0);
+    assert_eq!(client.get_subscription(&id2).balance, 10000 - 200 - 100);
+    assert_eq!(client.get_subscription(&id3).balance, 10000 - 200 - 100);
+}
+
+#[test]
+fn test_trigger_prices_notifies_on_threshold_breach() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let params = default_subscription(&e, &admin);
+    let (subscription_id, subscription) = client.create_subscription(&params, &10000);
+
+    let mut updates = soroban_sdk::Vec::new(&e);
+    updates.push_back((params.base.clone(), params.quote.clone(), 150i128, 100i128));
+    client.trigger_prices(&0, &updates);
+
+    let expected = (
+        client.address.clone(),
+        (REFLECTOR, soroban_sdk::symbol_short!("notified"), admin.clone()).into_val(&e),
+        (subscription_id, subscription.webhook.clone()).into_val(&e),
+    );
+    assert!(e.events().all().contains(&expected));
+}
+
+#[test]
+fn test_trigger_prices_notifies_on_heartbeat_elapsed() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let params = default_subscription(&e, &admin);
+    let (subscription_id, subscription) = client.create_subscription(&params, &10000);
+
+    // Same price both sides, so only the heartbeat can trigger the notification.
+    let mut updates = soroban_sdk::Vec::new(&e);
+    updates.push_back((params.base.clone(), params.quote.clone(), 100i128, 100i128));
+    let elapsed_timestamp = (params.heartbeat as u64) * 60 * 1000;
+    client.trigger_prices(&elapsed_timestamp, &updates);
+
+    let expected = (
+        client.address.clone(),
+        (REFLECTOR, soroban_sdk::symbol_short!("notified"), admin.clone()).into_val(&e),
+        (subscription_id, subscription.webhook.clone()).into_val(&e),
+    );
+    assert!(e.events().all().contains(&expected));
+}
+
+#[test]
+fn test_trigger_prices_prunes_suspended_and_renotifies_after_reactivation() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let params = default_subscription(&e, &admin);
+    // Init fee (200) plus half a fee (50) of runway, so the first heartbeat charge suspends it.
+    let (subscription_id, subscription) = client.create_subscription(&params, &250);
+    assert_eq!(client.get_subscription(&subscription_id).balance, 50);
+
+    e.ledger().with_mut(|l| l.timestamp += 3600); // one heartbeat (60 min) elapses
+    let mut ids = soroban_sdk::Vec::new(&e);
+    ids.push_back(subscription_id);
+    client.charge(&ids);
+    assert_eq!(
+        client.get_subscription(&subscription_id).status,
+        SubscriptionStatus::Suspended
+    );
+
+    // Still indexed until trigger_prices lazily prunes it.
+    let indexed = e.as_contract(&client.address, || {
+        e.get_indexed_subscriptions(&params.base, &params.quote)
+    });
+    assert!(indexed.contains(&subscription_id));
+
+    let mut updates = soroban_sdk::Vec::new(&e);
+    updates.push_back((params.base.clone(), params.quote.clone(), 200i128, 100i128));
+    let notified_topics = (REFLECTOR, soroban_sdk::symbol_short!("notified"), admin.clone());
+    let notified_data = (subscription_id, subscription.webhook.clone());
+
+    client.trigger_prices(&(e.ledger().timestamp() * 1000), &updates);
+
+    let indexed = e.as_contract(&client.address, || {
+        e.get_indexed_subscriptions(&params.base, &params.quote)
+    });
+    assert!(!indexed.contains(&subscription_id)); // pruned while suspended
+    let suspended_notified = (
+        client.address.clone(),
+        notified_topics.clone().into_val(&e),
+        notified_data.clone().into_val(&e),
+    );
+    assert!(!e.events().all().contains(&suspended_notified));
+
+    // Reactivating via deposit must re-index the subscription, or it would be silently
+    // dropped from future price notifications forever.
+    client.deposit(&admin, &subscription_id, &1000);
+    let indexed = e.as_contract(&client.address, || {
+        e.get_indexed_subscriptions(&params.base, &params.quote)
+    });
+    assert!(indexed.contains(&subscription_id));
+
+    client.trigger_prices(&(e.ledger().timestamp() * 1000), &updates);
+    let reactivated_notified = (
+        client.address.clone(),
+        notified_topics.into_val(&e),
+        notified_data.into_val(&e),
+    );
+    assert!(e.events().all().contains(&reactivated_notified));
+}
+
+#[test]
+fn test_subscription_index_enforces_cap() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let params = default_subscription(&e, &admin);
+
+    for _ in 0..MAX_INDEXED_SUBSCRIPTIONS {
+        client.create_subscription(&params, &300);
+    }
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        client.create_subscription(&params, &300)
+    }));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_deposit_reactivation_reclaims_slot_even_when_pair_index_is_full() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let (client, admin, _token) = init_contract(&e);
+    let params = default_subscription(&e, &admin);
+
+    let (subscription_id, _) = client.create_subscription(&params, &250);
+
+    // Fill the other slots of the pair's index with unrelated active subscriptions, so
+    // the index reaches the cap while `subscription_id` still holds its own slot.
+    for _ in 0..(MAX_INDEXED_SUBSCRIPTIONS - 1) {
+        client.create_subscription(&params, &300);
+    }
+
+    // Suspend it only now, so no later index call prunes it and it occupies a slot in
+    // the full index as Suspended.
+    e.ledger().with_mut(|l| l.timestamp += 3600); // one heartbeat (60 min) elapses
+    let mut ids = soroban_sdk::Vec::new(&e);
+    ids.push_back(subscription_id);
+    client.charge(&ids);
+    assert_eq!(
+        client.get_subscription(&subscription_id).status,
+        SubscriptionStatus::Suspended
+    );
+
+    // Reactivating must still succeed: `subscription_id` already holds a slot, even
+    // though it's currently Suspended and the other 199 slots are all taken.
+    client.deposit(&admin, &subscription_id, &1000);
+    assert_eq!(
+        client.get_subscription(&subscription_id).status,
+        SubscriptionStatus::Active
+    );
+}
@@ -0,0 +1,10 @@
+use soroban_sdk::{contracttype, Address, Symbol};
+
+// Mirrors the Reflector oracle's asset representation so subscriptions can
+// key off the same (base, quote) pairs the oracle reports prices for.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Asset {
+    Stellar(Address),
+    Other(Symbol),
+}
@@ -0,0 +1,20 @@
+use soroban_sdk::{contracttype, Address};
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct ContractConfig {
+    pub admin: Address,
+    pub fee: u64,
+    pub token: Address,
+    // Reflector price oracle contract address, read cross-contract to validate triggers
+    pub oracle: Address,
+    // Divisor applied to the update/threshold-weighted fee. Lets the admin
+    // retune the fee curve's overall scale without redeploying.
+    pub normalizer: u64,
+    // Optional ceiling on the `updates_per_day` factor, so a very short
+    // heartbeat can't blow the fee up unboundedly.
+    pub max_update_factor: Option<u64>,
+    // Optional ceiling on the `threshold_weight` factor, so a very tight
+    // threshold can't blow the fee up unboundedly.
+    pub max_threshold_factor: Option<u64>,
+}
@@ -0,0 +1,17 @@
+use soroban_sdk::contracterror;
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    AlreadyInitialized = 0,
+    NotInitialized = 1,
+    Unauthorized = 2,
+    InvalidAmount = 3,
+    InvalidHeartbeat = 4,
+    InvalidThreshold = 5,
+    WebhookTooLong = 6,
+    SubscriptionNotFound = 7,
+    InvalidSubscriptionStatusError = 8,
+    AssetPairIndexFull = 9,
+}
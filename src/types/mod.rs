@@ -0,0 +1,7 @@
+pub mod asset;
+pub mod contract_config;
+pub mod error;
+pub mod subscription;
+pub mod subscription_init_params;
+pub mod subscription_status;
+pub mod subscription_update_params;
@@ -0,0 +1,21 @@
+use soroban_sdk::{contracttype, Address, Bytes};
+
+use super::asset::Asset;
+use super::subscription_status::SubscriptionStatus;
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct Subscription {
+    pub owner: Address,
+    pub base: Asset,
+    pub quote: Asset,
+    pub threshold: u32,
+    pub heartbeat: u32,
+    pub webhook: Bytes,
+    pub balance: u64,
+    pub status: SubscriptionStatus,
+    pub updated: u64,
+    // Last price observed from the oracle when this subscription was served, used to
+    // measure the relative move since then. Zero until the first oracle-validated charge.
+    pub last_price: i128,
+}
@@ -0,0 +1,14 @@
+use soroban_sdk::{contracttype, Address, Bytes};
+
+use super::asset::Asset;
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SubscriptionInitParams {
+    pub owner: Address,
+    pub base: Asset,
+    pub quote: Asset,
+    pub threshold: u32,
+    pub heartbeat: u32,
+    pub webhook: Bytes,
+}
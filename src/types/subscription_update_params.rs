@@ -0,0 +1,13 @@
+use soroban_sdk::{contracttype, Bytes};
+
+use super::asset::Asset;
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct SubscriptionUpdateParams {
+    pub base: Asset,
+    pub quote: Asset,
+    pub threshold: u32,
+    pub heartbeat: u32,
+    pub webhook: Bytes,
+}